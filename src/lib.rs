@@ -1,11 +1,22 @@
-use std::{collections::VecDeque, fmt::Debug};
+use std::{
+    cmp::Ordering,
+    collections::{TryReserveError, VecDeque},
+    fmt::Debug,
+    rc::Rc,
+};
 
 #[derive(Clone, Debug)]
-struct BufferElement<F: PartialOrd + Clone + Debug> {
+struct BufferElement<F: Clone + Debug> {
     index: usize,
     value: F,
 }
 
+/// A user-supplied total ordering for `MaxDetector::with_comparator`.
+///
+/// `Rc` rather than `Box` so that `MaxDetector` itself can stay `Clone`,
+/// matching `MinMaxDetector`.
+type Comparator<F> = Rc<dyn Fn(&F, &F) -> Ordering>;
+
 /// Data structure to keep track of the max value over a ring buffer.
 /// Extension of a deque but for a new entry it will:
 ///   - Remove all elements that are now outside of the window.
@@ -15,27 +26,105 @@ struct BufferElement<F: PartialOrd + Clone + Debug> {
 /// This keeps the deque sorted and set to only the buffer giving
 /// efficiently returning of the max value.
 /// If two values are equal in their ordering, the newest value will be kept.
-#[derive(Clone, Debug)]
-pub struct MaxDetector<F: PartialOrd + Clone + Debug> {
+///
+/// Ordering is pluggable: `new` requires `F: PartialOrd`, while
+/// `with_comparator`/`with_key` accept any `F` and compare via a closure,
+/// which is how you get a sliding-window *minimum* (pass a reversed
+/// comparator), max-by-field on a struct, or a NaN-safe `f64::total_cmp`.
+#[derive(Clone)]
+pub struct MaxDetector<F: Clone + Debug> {
     deque: VecDeque<BufferElement<F>>,
     buffer_size: usize,
     next_index: usize,
+    cmp: Comparator<F>,
+    window: Option<VecDeque<F>>,
 }
 
-impl<F: PartialOrd + Clone + Debug> MaxDetector<F> {
-    pub fn new(buffer_size: usize) -> Self {
+impl<F: Clone + Debug> Debug for MaxDetector<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MaxDetector")
+            .field("deque", &self.deque)
+            .field("buffer_size", &self.buffer_size)
+            .field("next_index", &self.next_index)
+            .field("window", &self.window)
+            .finish()
+    }
+}
+
+impl<F: Clone + Debug> MaxDetector<F> {
+    /// Create a detector that orders values with `cmp` instead of `PartialOrd`.
+    pub fn with_comparator(buffer_size: usize, cmp: impl Fn(&F, &F) -> Ordering + 'static) -> Self {
         Self {
             buffer_size,
             deque: VecDeque::default(),
             next_index: 0,
+            cmp: Rc::new(cmp),
+            window: None,
         }
     }
 
+    /// Create a detector that orders values by a derived key, e.g.
+    /// `MaxDetector::with_key(buffer_size, |event| event.timestamp)`.
+    pub fn with_key<K: Ord>(buffer_size: usize, key: impl Fn(&F) -> K + 'static) -> Self {
+        Self::with_comparator(buffer_size, move |a, b| key(a).cmp(&key(b)))
+    }
+
+    /// Retain the raw values in the window (oldest to newest) alongside the
+    /// running max, so the window contents can be read back with `iter`,
+    /// `len`, `front`/`back` and `as_slices`. Off by default since most
+    /// callers only need the running max.
+    pub fn with_window_tracking(mut self) -> Self {
+        self.window = Some(VecDeque::with_capacity(self.buffer_size));
+        self
+    }
+
+    /// Iterate over the current window, oldest to newest.
+    ///
+    /// Empty unless `with_window_tracking` was used to build this detector.
+    pub fn iter(&self) -> impl Iterator<Item = &F> {
+        self.window.iter().flat_map(|window| window.iter())
+    }
+
+    /// Number of values currently retained in the window.
+    pub fn len(&self) -> usize {
+        self.window.as_ref().map_or(0, VecDeque::len)
+    }
+
+    /// Whether the window is empty (or window tracking is disabled).
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Oldest value still in the window.
+    pub fn front(&self) -> Option<&F> {
+        self.window.as_ref()?.front()
+    }
+
+    /// Newest value in the window.
+    pub fn back(&self) -> Option<&F> {
+        self.window.as_ref()?.back()
+    }
+
+    /// The window's contents as the two contiguous slices a `VecDeque` stores
+    /// internally, oldest to newest.
+    pub fn as_slices(&self) -> (&[F], &[F]) {
+        self.window
+            .as_ref()
+            .map_or((&[], &[]), VecDeque::as_slices)
+    }
+
     /// Add new element to buffer and return highest value.
     pub fn next(&mut self, value: F) -> F {
+        if let Some(window) = &mut self.window {
+            window.push_back(value.clone());
+            if window.len() > self.buffer_size {
+                window.pop_front();
+            }
+        }
         let deque = &mut self.deque;
         let buffer_size = self.buffer_size;
         let next_index = self.next_index;
+        let cmp = &self.cmp;
         // Remove values no longer in the buffer.
         // An element will only stay in the buffer long enough to require removal if its value is
         // the max value.
@@ -48,7 +137,7 @@ impl<F: PartialOrd + Clone + Debug> MaxDetector<F> {
                 index: next_index,
                 value,
             });
-        } else if deque.back().unwrap().value <= value {
+        } else if cmp(&deque.back().unwrap().value, &value) != Ordering::Greater {
             // New value is larger than max value.
             // Remove all other elements.
             deque.clear();
@@ -61,7 +150,7 @@ impl<F: PartialOrd + Clone + Debug> MaxDetector<F> {
             // Remove all elements with a value less than or equal to this entry.
             // This is okay as this value is larger and newer.
             // This also keeps the queue sorted and only retaining relevant elements.
-            while value >= deque.front().unwrap().value {
+            while cmp(&value, &deque.front().unwrap().value) != Ordering::Less {
                 deque.pop_front();
             }
             deque.push_front(BufferElement {
@@ -80,6 +169,216 @@ impl<F: PartialOrd + Clone + Debug> MaxDetector<F> {
         let value = self.deque.back()?;
         Some(value.value.to_owned())
     }
+
+    /// Get the current max value together with how many `next` calls ago it
+    /// entered the window, in `0..buffer_size`. A value of `0` means the max
+    /// was just set by the latest `next` call; it expires after
+    /// `buffer_size - age` further calls.
+    pub fn current_max_with_age(&self) -> Option<(F, usize)> {
+        let element = self.deque.back()?;
+        let age = (self.next_index + self.buffer_size - element.index - 1) % self.buffer_size;
+        Some((element.value.to_owned(), age))
+    }
+
+    /// Resize the window to `new_size`.
+    ///
+    /// Growing just raises the modulus used by future `next` calls. Shrinking
+    /// evicts any retained element whose age is now `>= new_size`, since it
+    /// no longer fits in the smaller window, while keeping the monotonic
+    /// ordering invariant of the rest.
+    ///
+    /// Internal indices are renormalized around the new `buffer_size`, so
+    /// callers don't need to do anything special before the next `next`
+    /// call.
+    pub fn resize(&mut self, new_size: usize) {
+        assert!(new_size > 0, "buffer_size must be positive");
+        let old_size = self.buffer_size;
+        let next_index = self.next_index;
+        let mut retained = VecDeque::with_capacity(new_size.min(self.deque.len()));
+        // Iterates front-to-back, i.e. newest-to-oldest, preserving order.
+        for element in &self.deque {
+            let age = (next_index + old_size - element.index - 1) % old_size;
+            if age < new_size {
+                retained.push_back(BufferElement {
+                    index: new_size - age - 1,
+                    value: element.value.clone(),
+                });
+            }
+        }
+        self.deque = retained;
+        self.buffer_size = new_size;
+        self.next_index = 0;
+        if let Some(window) = &mut self.window {
+            while window.len() > new_size {
+                window.pop_front();
+            }
+        }
+    }
+
+    /// Reserve capacity for at least `additional` more elements in the
+    /// internal deque, beyond the elements it currently holds. Useful after
+    /// `with_comparator`/`with_key`, which don't pre-allocate like
+    /// `with_capacity` does. Mirrors `VecDeque::reserve`.
+    pub fn reserve(&mut self, additional: usize) {
+        self.deque.reserve(additional);
+    }
+
+    /// Fallible version of `reserve`, for memory-constrained contexts.
+    /// Mirrors `VecDeque::try_reserve`.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.deque.try_reserve(additional)
+    }
+}
+
+impl<F: PartialOrd + Clone + Debug> MaxDetector<F> {
+    /// Incomparable values (e.g. `f64::NAN`) are treated as equal to
+    /// everything, so the usual "newest of equal values wins" tie-break
+    /// applies to them too. Callers that need stricter, panic-on-NaN
+    /// semantics, or a deterministic NaN ordering, should use
+    /// `with_comparator`/`with_key` with e.g. `f64::total_cmp`.
+    pub fn new(buffer_size: usize) -> Self {
+        Self::with_comparator(buffer_size, |a, b| {
+            a.partial_cmp(b).unwrap_or(Ordering::Equal)
+        })
+    }
+
+    /// Like `new`, but pre-allocates the internal deque for `buffer_size`
+    /// elements, so the monotonic deque never reallocates during
+    /// steady-state `next` calls even in the worst case (a descending
+    /// stream of values, which keeps every element).
+    pub fn with_capacity(buffer_size: usize) -> Self {
+        let mut detector = Self::new(buffer_size);
+        detector.deque = VecDeque::with_capacity(buffer_size);
+        detector
+    }
+}
+
+/// Data structure to keep track of both the min and the max value over a ring buffer.
+///
+/// Mirrors `MaxDetector` but maintains two monotonic deques: a *decreasing*
+/// deque (front = newest, back = current max) and an *increasing* deque
+/// (front = newest, back = current min). Both are maintained with the same
+/// three-step rule on every `next`:
+///   - Remove the back element if it has aged out of the window.
+///   - Remove front elements that are no longer extremal now that a new
+///     value has arrived.
+///   - Push the new value to the front.
+///
+/// If two values are equal in their ordering, the newest value will be kept.
+#[derive(Clone, Debug)]
+pub struct MinMaxDetector<F: PartialOrd + Clone + Debug> {
+    max_deque: VecDeque<BufferElement<F>>,
+    min_deque: VecDeque<BufferElement<F>>,
+    buffer_size: usize,
+    next_index: usize,
+}
+
+impl<F: PartialOrd + Clone + Debug> MinMaxDetector<F> {
+    pub fn new(buffer_size: usize) -> Self {
+        Self {
+            buffer_size,
+            max_deque: VecDeque::default(),
+            min_deque: VecDeque::default(),
+            next_index: 0,
+        }
+    }
+
+    /// Add a new element to the buffer and return the current `(min, max)`.
+    pub fn next(&mut self, value: F) -> (F, F) {
+        let next_index = self.next_index;
+        Self::push_max(&mut self.max_deque, next_index, value.clone());
+        Self::push_min(&mut self.min_deque, next_index, value);
+        // Update next index in ring buffer.
+        self.next_index = (next_index + 1) % self.buffer_size;
+        (
+            self.min_deque.back().unwrap().value.to_owned(),
+            self.max_deque.back().unwrap().value.to_owned(),
+        )
+    }
+
+    fn push_max(deque: &mut VecDeque<BufferElement<F>>, next_index: usize, value: F) {
+        // Remove values no longer in the buffer.
+        // An element will only stay in the buffer long enough to require removal if its value is
+        // the max value.
+        // Therefore we only need to check the max value element (back of queue).
+        if deque.back().map(|it| it.index) == Some(next_index) {
+            deque.pop_back();
+        }
+        if deque.is_empty() {
+            deque.push_back(BufferElement {
+                index: next_index,
+                value,
+            });
+        } else if deque.back().unwrap().value <= value {
+            // New value is larger than max value.
+            // Remove all other elements.
+            deque.clear();
+            deque.push_back(BufferElement {
+                index: next_index,
+                value,
+            });
+        } else {
+            // Add element to queue from left.
+            // Remove all elements with a value less than or equal to this entry.
+            // This is okay as this value is larger and newer.
+            // This also keeps the queue sorted and only retaining relevant elements.
+            while value >= deque.front().unwrap().value {
+                deque.pop_front();
+            }
+            deque.push_front(BufferElement {
+                index: next_index,
+                value,
+            });
+        }
+    }
+
+    fn push_min(deque: &mut VecDeque<BufferElement<F>>, next_index: usize, value: F) {
+        // Remove values no longer in the buffer.
+        // An element will only stay in the buffer long enough to require removal if its value is
+        // the min value.
+        // Therefore we only need to check the min value element (back of queue).
+        if deque.back().map(|it| it.index) == Some(next_index) {
+            deque.pop_back();
+        }
+        if deque.is_empty() {
+            deque.push_back(BufferElement {
+                index: next_index,
+                value,
+            });
+        } else if deque.back().unwrap().value >= value {
+            // New value is smaller than min value.
+            // Remove all other elements.
+            deque.clear();
+            deque.push_back(BufferElement {
+                index: next_index,
+                value,
+            });
+        } else {
+            // Add element to queue from left.
+            // Remove all elements with a value greater than or equal to this entry.
+            // This is okay as this value is smaller and newer.
+            // This also keeps the queue sorted and only retaining relevant elements.
+            while value <= deque.front().unwrap().value {
+                deque.pop_front();
+            }
+            deque.push_front(BufferElement {
+                index: next_index,
+                value,
+            });
+        }
+    }
+
+    /// Get current max value in buffer.
+    pub fn current_max(&self) -> Option<F> {
+        let value = self.max_deque.back()?;
+        Some(value.value.to_owned())
+    }
+
+    /// Get current min value in buffer.
+    pub fn current_min(&self) -> Option<F> {
+        let value = self.min_deque.back()?;
+        Some(value.value.to_owned())
+    }
 }
 
 #[cfg(test)]
@@ -215,4 +514,241 @@ mod test {
         });
         assert_eq!(detector.current(), expected);
     }
+
+    #[test]
+    fn with_comparator_reversed_tracks_minimum() {
+        let array = [0.5, 0.4, 0.3, 0.2, 0.1];
+        let mut detector = MaxDetector::with_comparator(10, |a: &f64, b: &f64| {
+            b.partial_cmp(a).unwrap()
+        });
+        let detected_mins = array.map(|it| detector.next(it));
+        assert_eq!(detected_mins, [0.5, 0.4, 0.3, 0.2, 0.1]);
+    }
+
+    #[test]
+    fn with_key_tracks_max_by_field() {
+        #[derive(Debug, Clone, PartialEq)]
+        struct Event {
+            timestamp: u64,
+        }
+
+        let events = [
+            Event { timestamp: 3 },
+            Event { timestamp: 1 },
+            Event { timestamp: 5 },
+            Event { timestamp: 2 },
+        ];
+        let mut detector = MaxDetector::with_key(10, |event: &Event| event.timestamp);
+        for event in events {
+            detector.next(event);
+        }
+        assert_eq!(detector.current(), Some(Event { timestamp: 5 }));
+    }
+
+    #[test]
+    fn with_comparator_uses_total_cmp_for_floats() {
+        // `f64::total_cmp` gives a deterministic order for NaN instead of the
+        // `PartialOrd` comparisons (`<=`/`>=`) silently treating it as neither
+        // greater nor smaller than anything.
+        let array = [0.1, 0.5, 0.2];
+        let mut detector = MaxDetector::with_comparator(10, f64::total_cmp);
+        for value in array {
+            detector.next(value);
+        }
+        assert_eq!(detector.current(), Some(0.5));
+        assert!(detector.next(f64::NAN).is_nan());
+    }
+
+    #[test]
+    fn window_tracking_is_off_by_default() {
+        let mut detector = MaxDetector::new(4);
+        for value in [0.1, 0.2, 0.3] {
+            detector.next(value);
+        }
+        assert!(detector.is_empty());
+        assert_eq!(detector.len(), 0);
+        assert_eq!(detector.front(), None);
+    }
+
+    #[test]
+    fn window_tracking_exposes_sliding_contents() {
+        let mut detector = MaxDetector::new(3).with_window_tracking();
+        for value in [0.1, 0.2, 0.3, 0.4] {
+            detector.next(value);
+        }
+        assert_eq!(detector.len(), 3);
+        assert!(!detector.is_empty());
+        assert_eq!(detector.front(), Some(&0.2));
+        assert_eq!(detector.back(), Some(&0.4));
+        assert_eq!(
+            detector.iter().copied().collect::<Vec<_>>(),
+            vec![0.2, 0.3, 0.4]
+        );
+        assert_eq!(detector.as_slices().0, &[0.2, 0.3, 0.4]);
+    }
+
+    #[test]
+    fn current_max_with_age_tracks_how_long_ago_the_max_entered() {
+        let mut detector = MaxDetector::new(4);
+        assert_eq!(detector.next(0.5), 0.5);
+        assert_eq!(detector.current_max_with_age(), Some((0.5, 0)));
+        detector.next(0.1);
+        assert_eq!(detector.current_max_with_age(), Some((0.5, 1)));
+        detector.next(0.2);
+        assert_eq!(detector.current_max_with_age(), Some((0.5, 2)));
+    }
+
+    #[test]
+    fn current_max_with_age_resets_when_a_new_max_arrives() {
+        let mut detector = MaxDetector::new(4);
+        detector.next(0.1);
+        detector.next(0.2);
+        detector.next(0.5);
+        assert_eq!(detector.current_max_with_age(), Some((0.5, 0)));
+    }
+
+    #[test]
+    fn current_max_with_age_is_none_for_empty_buffer() {
+        let detector = MaxDetector::<f64>::new(4);
+        assert_eq!(detector.current_max_with_age(), None);
+    }
+
+    #[test]
+    fn resize_grow_keeps_tracking_the_max() {
+        let mut detector = MaxDetector::new(4);
+        for value in [0.1, 0.2, 0.3, 0.1] {
+            detector.next(value);
+        }
+        assert_eq!(detector.current(), Some(0.3));
+        detector.resize(6);
+        assert_eq!(detector.current(), Some(0.3));
+        for value in [0.1, 0.1] {
+            detector.next(value);
+        }
+        // Still in the enlarged window.
+        assert_eq!(detector.current(), Some(0.3));
+    }
+
+    #[test]
+    fn resize_shrink_evicts_values_that_no_longer_fit() {
+        let mut detector = MaxDetector::new(5);
+        for value in [0.5, 0.1, 0.2, 0.3] {
+            detector.next(value);
+        }
+        assert_eq!(detector.current(), Some(0.5));
+        // 0.5 is now 3 steps old; shrinking to 3 should evict it.
+        detector.resize(3);
+        assert_eq!(detector.current(), Some(0.3));
+    }
+
+    #[test]
+    fn resize_shrink_then_grow_stays_correct() {
+        let mut detector = MaxDetector::new(4).with_window_tracking();
+        for value in [0.1, 0.4, 0.2, 0.3] {
+            detector.next(value);
+        }
+        detector.resize(2);
+        assert_eq!(detector.current(), Some(0.3));
+        assert_eq!(detector.len(), 2);
+        assert_eq!(detector.as_slices().0, &[0.2, 0.3]);
+        detector.resize(4);
+        detector.next(0.05);
+        assert_eq!(detector.current(), Some(0.3));
+    }
+
+    #[test]
+    fn with_capacity_preallocates_and_still_tracks_max() {
+        let mut detector = MaxDetector::with_capacity(4);
+        assert!(detector.deque.capacity() >= 4);
+        let array = [0.5, 0.4, 0.3, 0.2, 0.1];
+        for value in array {
+            detector.next(value);
+        }
+        assert_eq!(detector.current(), Some(0.4));
+    }
+
+    #[test]
+    fn reserve_grows_deque_capacity() {
+        let mut detector = MaxDetector::with_comparator(4, |a: &f64, b: &f64| a.partial_cmp(b).unwrap());
+        detector.reserve(10);
+        assert!(detector.deque.capacity() >= 10);
+    }
+
+    #[test]
+    fn try_reserve_reports_success() {
+        let mut detector = MaxDetector::<f64>::new(4);
+        assert!(detector.try_reserve(4).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod min_max_test {
+    use super::MinMaxDetector;
+
+    #[test]
+    fn tracks_min_and_max_ascending_list() {
+        let array = [0.0, 0.1, 0.2, 0.3, 0.4, 0.5];
+        let mut detector = MinMaxDetector::new(10);
+        let detected = array.map(|it| detector.next(it));
+        assert_eq!(
+            detected,
+            [
+                (0.0, 0.0),
+                (0.0, 0.1),
+                (0.0, 0.2),
+                (0.0, 0.3),
+                (0.0, 0.4),
+                (0.0, 0.5),
+            ]
+        );
+    }
+
+    #[test]
+    fn tracks_min_and_max_descending_list() {
+        let array = [0.5, 0.4, 0.3, 0.2, 0.1];
+        let mut detector = MinMaxDetector::new(10);
+        let detected = array.map(|it| detector.next(it));
+        assert_eq!(
+            detected,
+            [
+                (0.5, 0.5),
+                (0.4, 0.5),
+                (0.3, 0.5),
+                (0.2, 0.5),
+                (0.1, 0.5),
+            ]
+        );
+    }
+
+    #[test]
+    fn min_and_max_outside_of_buffer_are_removed() {
+        let array = [0.5, 0.1, 0.2, 0.3, 0.4];
+        let mut detector = MinMaxDetector::new(4);
+        for value in array {
+            detector.next(value);
+        }
+        assert_eq!(detector.current_min(), Some(0.1));
+        assert_eq!(detector.current_max(), Some(0.4));
+    }
+
+    #[test]
+    fn detector_keeps_newest_of_equal_values() {
+        let values = [1, 3, 1, 3];
+        let mut detector = MinMaxDetector::new(5);
+        for value in values {
+            detector.next(value);
+        }
+        assert_eq!(detector.current_min(), Some(1));
+        assert_eq!(detector.current_max(), Some(3));
+        // The retained extremes are the later copies (indices 2 and 3).
+        assert_eq!(detector.min_deque.back().unwrap().index, 2);
+        assert_eq!(detector.max_deque.back().unwrap().index, 3);
+    }
+
+    #[test]
+    fn empty_buffer_returns_none() {
+        let detector = MinMaxDetector::<f32>::new(10);
+        assert_eq!(detector.current_min(), None);
+        assert_eq!(detector.current_max(), None);
+    }
 }